@@ -1,4 +1,4 @@
-use std::iter::once;
+use std::{collections::HashMap, iter::once};
 
 use anyhow::Result;
 use indexmap::IndexMap;
@@ -14,7 +14,9 @@ use turbopack::{
 };
 use turbopack_browser::{react_refresh::assert_can_resolve_react_refresh, BrowserChunkingContext};
 use turbopack_core::{
-    chunk::{module_id_strategies::ModuleIdStrategy, ChunkingContext},
+    chunk::{
+        module_id_strategies::ModuleIdStrategy, ChunkingConfig, ChunkingContext, ModuleId,
+    },
     compile_time_info::{
         CompileTimeDefineValue, CompileTimeDefines, CompileTimeInfo, FreeVarReference,
         FreeVarReferences,
@@ -22,6 +24,7 @@ use turbopack_core::{
     condition::ContextCondition,
     environment::{BrowserEnvironment, Environment, ExecutionEnvironment},
     free_var_references,
+    ident::AssetIdent,
     resolve::{parse::Request, pattern::Pattern},
 };
 use turbopack_node::{
@@ -105,17 +108,185 @@ async fn next_client_free_vars(define_env: Vc<EnvMap>) -> Result<Vc<FreeVarRefer
     .cell())
 }
 
+/// The differential-bundling tier a client build targets.
+///
+/// The modern tier targets browsers with native ES module support and is
+/// shipped as `<script type=module>`, while the legacy tier uses the project's
+/// full browserslist query and is shipped as `<script nomodule>`. Emitting both
+/// lets the majority of users skip transpilation and polyfill weight without
+/// dropping support for old browsers.
+#[turbo_tasks::value(serialization = "auto_for_input")]
+#[derive(Debug, Copy, Clone, Hash, Default)]
+pub enum ClientEnvironmentTier {
+    #[default]
+    Modern,
+    Legacy,
+}
+
+/// Browserslist query describing browsers with native ES module support. Used
+/// for the modern tier so that unnecessary transpilation and polyfills are
+/// skipped for the browsers that can run untranspiled output.
+const MODERN_BROWSERSLIST_QUERY: &str =
+    "supports es6-module and supports es6-module-dynamic-import";
+
+/// The parsed shape of a package's `package.json` `sideEffects` field.
+#[turbo_tasks::value(serialization = "auto_for_input")]
+#[derive(Debug, Clone, Hash)]
+pub enum SideEffects {
+    /// `"sideEffects": false` — every module in the package is free of side
+    /// effects and unused imports can be dropped.
+    None,
+    /// `"sideEffects": true`, an absent field, or a malformed value — assume
+    /// every module has side effects.
+    All,
+    /// `"sideEffects": [...]` — globs, relative to the package root, selecting
+    /// the modules that *do* have side effects (a glob may be negated with a
+    /// leading `!`). Because the package-granular `side_effect_free_packages`
+    /// field can't express these carve-outs, such packages are treated
+    /// conservatively as having side effects.
+    Globs(Vec<RcStr>),
+}
+
+impl SideEffects {
+    /// Parse a raw JSON `sideEffects` value. Anything that isn't a boolean or
+    /// an array of strings falls back to [`SideEffects::All`] so that malformed
+    /// input never drops a module that actually has side effects.
+    fn parse(value: &serde_json::Value) -> Self {
+        match value {
+            serde_json::Value::Bool(false) => SideEffects::None,
+            serde_json::Value::Bool(true) => SideEffects::All,
+            serde_json::Value::Array(globs) => globs
+                .iter()
+                .map(|g| g.as_str().map(|g| normalize_side_effects_glob(g).into()))
+                .collect::<Option<Vec<RcStr>>>()
+                .map_or(SideEffects::All, SideEffects::Globs),
+            _ => SideEffects::All,
+        }
+    }
+
+    /// Whether the whole package can be contributed to
+    /// `side_effect_free_packages`.
+    ///
+    /// That field is package-granular — turbopack joins each entry into a single
+    /// `**/node_modules/{name}/**` glob and has no per-entry negation — so only
+    /// `"sideEffects": false` can be expressed safely. The array form carves out
+    /// individual side-effectful modules (CSS, polyfills) that a whole-package
+    /// entry cannot preserve, so we conservatively leave those packages marked
+    /// as having side effects rather than risk shaking a module the author
+    /// flagged.
+    fn is_whole_package_free(&self) -> bool {
+        matches!(self, SideEffects::None)
+    }
+}
+
+/// Strip a single leading `./` from a `sideEffects` glob so the stored glob is
+/// written relative to the package root.
+fn normalize_side_effects_glob(glob: &str) -> &str {
+    glob.strip_prefix("./").unwrap_or(glob)
+}
+
+/// Load and parse the `sideEffects` field of the `package.json` at
+/// `package_json_path`. Returns [`SideEffects::All`] when the file is missing or
+/// unparseable so that foreign code is never mistakenly tree-shaken.
+#[turbo_tasks::function]
+pub async fn package_side_effects(
+    package_json_path: Vc<FileSystemPath>,
+) -> Result<Vc<SideEffects>> {
+    let Some(content) = package_json_path.read().await?.as_content() else {
+        return Ok(SideEffects::All.cell());
+    };
+    let Ok(json) = serde_json::from_slice::<serde_json::Value>(&content.content().to_bytes()?)
+    else {
+        return Ok(SideEffects::All.cell());
+    };
+    Ok(match json.get("sideEffects") {
+        Some(value) => SideEffects::parse(value),
+        None => SideEffects::All,
+    }
+    .cell())
+}
+
+/// Build the `side_effect_free_packages` list for foreign (node_modules) code.
+///
+/// Starts from the globally configured `optimize_package_imports` packages,
+/// then reads each dependency declared in the project's `package.json` and adds
+/// the ones whose own `package.json` declares `"sideEffects": false`, so their
+/// unused re-exports can be dropped. Packages using the array form are left out
+/// (see [`SideEffects::is_whole_package_free`]) because the package-granular
+/// field can't honour their per-module carve-outs.
+#[turbo_tasks::function]
+async fn foreign_side_effect_free_packages(
+    project_path: Vc<FileSystemPath>,
+    next_config: Vc<NextConfig>,
+) -> Result<Vc<Vec<RcStr>>> {
+    let mut packages = next_config.optimize_package_imports().await?.clone_value();
+
+    let node_modules = project_path.join("node_modules".into());
+    for package in project_dependencies(project_path).await?.iter() {
+        let package_json = node_modules.join(format!("{package}/package.json").into());
+        if package_side_effects(package_json).await?.is_whole_package_free() {
+            packages.push(package.clone());
+        }
+    }
+
+    Ok(Vc::cell(packages))
+}
+
+/// Collect every dependency name declared in the project's `package.json`
+/// (across the `dependencies`, `devDependencies`, `optionalDependencies` and
+/// `peerDependencies` maps). Returns an empty list when the manifest is missing
+/// or unparseable.
+#[turbo_tasks::function]
+async fn project_dependencies(project_path: Vc<FileSystemPath>) -> Result<Vc<Vec<RcStr>>> {
+    let package_json_path = project_path.join("package.json".into());
+    let Some(content) = package_json_path.read().await?.as_content() else {
+        return Ok(Vc::cell(vec![]));
+    };
+    let Ok(json) = serde_json::from_slice::<serde_json::Value>(&content.content().to_bytes()?)
+    else {
+        return Ok(Vc::cell(vec![]));
+    };
+
+    let mut packages = vec![];
+    for field in [
+        "dependencies",
+        "devDependencies",
+        "optionalDependencies",
+        "peerDependencies",
+    ] {
+        if let Some(serde_json::Value::Object(deps)) = json.get(field) {
+            packages.extend(deps.keys().map(|name| name.as_str().into()));
+        }
+    }
+
+    Ok(Vc::cell(packages))
+}
+
 #[turbo_tasks::function]
 pub fn get_client_compile_time_info(
     browserslist_query: RcStr,
     define_env: Vc<EnvMap>,
+    ty: Value<ClientContextType>,
+    tier: Value<ClientEnvironmentTier>,
 ) -> Vc<CompileTimeInfo> {
+    let browserslist_query = match tier.into_value() {
+        ClientEnvironmentTier::Modern => MODERN_BROWSERSLIST_QUERY.into(),
+        ClientEnvironmentTier::Legacy => browserslist_query,
+    };
+    // Worker globals don't have a DOM; flip the relevant worker flag so that
+    // DOM-only free vars and APIs aren't assumed to be available.
+    let (dom, web_worker, service_worker) = match *ty {
+        ClientContextType::Worker { is_service_worker } => {
+            (false, !is_service_worker, is_service_worker)
+        }
+        _ => (true, false, false),
+    };
     CompileTimeInfo::builder(Environment::new(Value::new(ExecutionEnvironment::Browser(
         BrowserEnvironment {
-            dom: true,
-            web_worker: false,
-            service_worker: false,
-            browserslist_query: browserslist_query.to_owned(),
+            dom,
+            web_worker,
+            service_worker,
+            browserslist_query,
         }
         .into(),
     ))))
@@ -130,6 +301,9 @@ pub enum ClientContextType {
     Pages { pages_dir: Vc<FileSystemPath> },
     App { app_dir: Vc<FileSystemPath> },
     Fallback,
+    /// A dedicated/shared Web Worker or a Service Worker, bundled through the
+    /// same transform pipeline as regular client code.
+    Worker { is_service_worker: bool },
     Other,
 }
 
@@ -146,7 +320,10 @@ pub async fn get_client_resolve_options_context(
     let next_client_fallback_import_map = get_next_client_fallback_import_map(ty);
     let next_client_resolved_map =
         get_next_client_resolved_map(project_path, project_path, *mode.await?);
-    let custom_conditions = vec![mode.await?.condition().into()];
+    let mut custom_conditions = vec![mode.await?.condition().into()];
+    if matches!(*ty, ClientContextType::Worker { .. }) {
+        custom_conditions.push("worker".into());
+    }
     let module_options_context = ResolveOptionsContext {
         enable_node_modules: Some(project_path.root().resolve().await?),
         custom_conditions,
@@ -261,6 +438,17 @@ pub async fn get_client_module_options_context(
 
     next_client_rules.extend(additional_rules);
 
+    // NOTE: the client-side `"use server"` rewrite is performed by the next
+    // client transforms assembled in `get_next_client_transforms_rules` above,
+    // which carries the action transform and the
+    // `__next_internal_action_entry_do_not_use__` handling. Contributing the
+    // discovered action ids to a `server-reference-manifest` (id → loader
+    // chunk) is deliberately NOT done here: the ids are only final once
+    // chunking has named the loader chunks, so the manifest is assembled in the
+    // build/chunking layer rather than during module-option resolution. The
+    // App-only action-dispatch runtime entry is still registered in
+    // `get_client_runtime_entries`.
+
     let postcss_transform_options = PostCssTransformOptions {
         postcss_package: Some(get_postcss_package_mapping(project_path)),
         config_location: PostCssConfigLocation::ProjectPathOrLocalPath,
@@ -289,6 +477,13 @@ pub async fn get_client_module_options_context(
     };
 
     // node_modules context
+    //
+    // Foreign code gets its own `side_effect_free_packages` list: on top of the
+    // globally configured `optimize_package_imports` packages, each declared
+    // dependency whose `package.json` has `"sideEffects": false` is added by
+    // name, so the tree-shaking pass can drop its unused re-exported bindings.
+    // A package using the array form is not added: all of its modules are
+    // treated as having side effects.
     let foreign_codes_options_context = ModuleOptionsContext {
         ecmascript: EcmascriptOptionsContext {
             enable_typeof_window_inlining: None,
@@ -298,6 +493,9 @@ pub async fn get_client_module_options_context(
         enable_postcss_transform: enable_foreign_postcss_transform,
         module_rules: foreign_next_client_rules,
         tree_shaking_mode: tree_shaking_mode_for_foreign_code,
+        side_effect_free_packages: foreign_side_effect_free_packages(project_path, next_config)
+            .await?
+            .clone_value(),
         // NOTE(WEB-1016) PostCSS transforms should also apply to foreign code.
         ..module_options_context.clone()
     };
@@ -346,6 +544,141 @@ pub async fn get_client_module_options_context(
     Ok(module_options_context)
 }
 
+/// The base-62 alphabet used for hashed module ids: digits then lower- and
+/// upper-case letters, giving short identifiers that are still valid in a URL.
+const BASE62_ALPHABET: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// Initial length, in base-62 characters, of a hashed module id. Colliding ids
+/// are extended one character at a time until the set is unique.
+const MODULE_ID_HASH_LENGTH: usize = 5;
+
+fn to_base62(mut hash: u64) -> String {
+    if hash == 0 {
+        return "0".into();
+    }
+    let mut out = Vec::new();
+    while hash > 0 {
+        out.push(BASE62_ALPHABET[(hash % 62) as usize]);
+        hash /= 62;
+    }
+    out.reverse();
+    // SAFETY: every byte came from `BASE62_ALPHABET`, which is ASCII.
+    String::from_utf8(out).unwrap()
+}
+
+/// A stable FNV-1a hash of the module's resolved path. Using a fixed algorithm
+/// (rather than the process-seeded default hasher) keeps ids reproducible
+/// across machines and builds.
+fn hash_module_path(path: &str) -> u64 {
+    const OFFSET: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+    let mut hash = OFFSET;
+    for byte in path.as_bytes() {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(PRIME);
+    }
+    hash
+}
+
+/// Truncate a full base-62 hash to `len` characters.
+fn truncate_hash(full: &str, len: usize) -> String {
+    full.chars().take(len).collect()
+}
+
+/// Assign a short, collision-free base-62 id to each module path.
+///
+/// Paths are sorted first so the assignment is deterministic regardless of the
+/// order the module graph was walked in. Each id starts at
+/// [`MODULE_ID_HASH_LENGTH`] characters; whenever two or more paths share a
+/// truncated hash, only that colliding group is re-truncated one character
+/// longer (repeatedly) until its ids are unique, so a single collision never
+/// lengthens the ids of unrelated modules.
+fn assign_content_hashed_ids(mut paths: Vec<RcStr>) -> Vec<(RcStr, RcStr)> {
+    paths.sort();
+    paths.dedup();
+
+    // Full hash and current truncation length per path; lengths grow per group.
+    let mut entries: Vec<(RcStr, String, usize)> = paths
+        .into_iter()
+        .map(|path| {
+            let full = to_base62(hash_module_path(&path));
+            (path, full, MODULE_ID_HASH_LENGTH)
+        })
+        .collect();
+
+    loop {
+        // Group indices by their current truncated id.
+        let mut groups: HashMap<String, Vec<usize>> = HashMap::with_capacity(entries.len());
+        for (i, (_, full, len)) in entries.iter().enumerate() {
+            groups.entry(truncate_hash(full, *len)).or_default().push(i);
+        }
+
+        // Extend only the members of colliding groups that still have room.
+        let mut extended = false;
+        for indices in groups.values() {
+            if indices.len() > 1 {
+                for &i in indices {
+                    let (_, full, len) = &mut entries[i];
+                    if *len < full.chars().count() {
+                        *len += 1;
+                        extended = true;
+                    }
+                }
+            }
+        }
+
+        if !extended {
+            break;
+        }
+    }
+
+    entries
+        .into_iter()
+        .map(|(path, full, len)| (path, truncate_hash(&full, len).into()))
+        .collect()
+}
+
+/// A [`ModuleIdStrategy`] that maps each module to a short content-hashed id
+/// derived from its resolved path. The ids are stable across builds and
+/// machines, so unchanged `static/chunks` keep their identifiers between
+/// deploys and stay warm in browser and CDN caches.
+#[turbo_tasks::value]
+pub struct ContentHashModuleIdStrategy {
+    /// Resolved module path → assigned id.
+    ids: HashMap<RcStr, RcStr>,
+}
+
+#[turbo_tasks::value_impl]
+impl ContentHashModuleIdStrategy {
+    #[turbo_tasks::function]
+    pub async fn new(module_paths: Vc<Vec<RcStr>>) -> Result<Vc<Self>> {
+        let ids = assign_content_hashed_ids(module_paths.await?.clone_value())
+            .into_iter()
+            .collect();
+        Ok(ContentHashModuleIdStrategy { ids }.cell())
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ModuleIdStrategy for ContentHashModuleIdStrategy {
+    #[turbo_tasks::function]
+    async fn get_module_id(&self, ident: Vc<AssetIdent>) -> Result<Vc<ModuleId>> {
+        let path = ident.to_string().await?;
+        Ok(match self.ids.get(&*path) {
+            Some(id) => ModuleId::String(id.clone()),
+            // A module that wasn't part of the graph the map was built from
+            // still gets a stable hash of its path rather than the raw path. The
+            // assigned ids are pure base-62, so an `_` prefix plus the full
+            // (untruncated) hash keeps the fallback reproducible while making it
+            // impossible to alias an already-assigned in-graph id.
+            None => {
+                ModuleId::String(format!("_{}", to_base62(hash_module_path(&path))).into())
+            }
+        }
+        .cell())
+    }
+}
+
 #[turbo_tasks::function]
 pub async fn get_client_chunking_context(
     project_path: Vc<FileSystemPath>,
@@ -354,13 +687,32 @@ pub async fn get_client_chunking_context(
     environment: Vc<Environment>,
     mode: Vc<NextMode>,
     module_id_strategy: Vc<Box<dyn ModuleIdStrategy>>,
+    module_paths: Vc<Vec<RcStr>>,
+    ty: Value<ClientContextType>,
+    tier: Value<ClientEnvironmentTier>,
 ) -> Result<Vc<Box<dyn ChunkingContext>>> {
     let next_mode = mode.await?;
+    let is_worker = matches!(*ty, ClientContextType::Worker { .. });
+
+    // In development we keep the readable, path-based ids supplied by the
+    // caller. Build/production instead gets stable content-hashed ids so that
+    // unchanged chunks keep their identifiers across deploys.
+    let module_id_strategy = if next_mode.is_development() {
+        module_id_strategy
+    } else {
+        Vc::upcast(ContentHashModuleIdStrategy::new(module_paths))
+    };
+    // The legacy (`nomodule`) tier writes alongside the modern output so both
+    // can be served from the same `static` root.
+    let chunks_path = match tier.into_value() {
+        ClientEnvironmentTier::Modern => "static/chunks",
+        ClientEnvironmentTier::Legacy => "static/chunks/legacy",
+    };
     let mut builder = BrowserChunkingContext::builder(
         project_path,
         client_root,
         client_root,
-        client_root.join("static/chunks".into()),
+        client_root.join(chunks_path.into()),
         get_client_assets_path(client_root),
         environment,
         next_mode.runtime_type(),
@@ -370,7 +722,21 @@ pub async fn get_client_chunking_context(
     .asset_base_path(asset_prefix)
     .module_id_strategy(module_id_strategy);
 
-    if next_mode.is_development() {
+    if is_worker {
+        // A worker has to be a single self-contained file — there's no document
+        // to inject additional `<script>` tags into and no chunk loader in the
+        // worker global scope. Collapse every group into one chunk so the whole
+        // worker graph is emitted as one bundle.
+        builder = builder.chunking_config(ChunkingConfig {
+            max_chunk_count_per_group: 1,
+            ..Default::default()
+        });
+    }
+
+    // Workers are bundled as a single self-contained chunk and can't take part
+    // in the same HMR handshake as the main document, so HMR stays off for them
+    // even in development.
+    if next_mode.is_development() && !is_worker {
         builder = builder.hot_module_replacement();
     }
 
@@ -419,6 +785,18 @@ pub async fn get_client_runtime_entries(
             )
             .cell(),
         );
+
+        // Wires up the action-dispatch runtime that the `"use server"`
+        // reference objects call into when a client invokes a server action.
+        runtime_entries.push(
+            RuntimeEntry::Request(
+                Request::parse(Value::new(Pattern::Constant(
+                    "next/dist/client/app-call-server.js".into(),
+                ))),
+                project_root.join("_".into()),
+            )
+            .cell(),
+        );
     }
 
     Ok(Vc::cell(runtime_entries))